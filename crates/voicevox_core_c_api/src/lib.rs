@@ -1,7 +1,11 @@
 /// cbindgen:ignore
 mod compatible_engine;
 mod helpers;
+#[cfg(feature = "playback")]
+mod playback;
 use self::helpers::*;
+#[cfg(feature = "playback")]
+pub use self::playback::*;
 use libc::c_void;
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -21,7 +25,15 @@ use rstest::*;
 
 type Internal = VoicevoxCore;
 
-static INTERNAL: Lazy<Mutex<Internal>> = Lazy::new(|| {
+/// 複数のインスタンスを同時に扱うための不透明なハンドル
+///
+/// `voicevox_initialize`・`voicevox_load_model`・`voicevox_audio_query`・`voicevox_synthesis`・
+/// `voicevox_tts` 等のグローバル関数は、プロセス内で1つだけ遅延生成される `DEFAULT_HANDLE` を
+/// 介してこの型の薄いラッパーとして実装されている。`voicevox_core_new` で生成したハンドルは
+/// `DEFAULT_HANDLE` と状態を共有しない独立したインスタンスである
+pub struct VoicevoxCoreHandle(Mutex<Internal>);
+
+static DEFAULT_HANDLE: Lazy<VoicevoxCoreHandle> = Lazy::new(|| {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(if env::var_os(EnvFilter::DEFAULT_ENV).is_some() {
             EnvFilter::from_default_env()
@@ -31,11 +43,167 @@ static INTERNAL: Lazy<Mutex<Internal>> = Lazy::new(|| {
         .with_writer(io::stderr)
         .try_init();
 
-    Internal::new_with_mutex()
+    VoicevoxCoreHandle(Internal::new_with_mutex())
 });
 
 pub(crate) fn lock_internal() -> MutexGuard<'static, Internal> {
-    INTERNAL.lock().unwrap()
+    DEFAULT_HANDLE.0.lock().unwrap()
+}
+
+/// 新しい #VoicevoxCoreHandle を生成する
+/// @param [in] options 初期化オプション
+/// @param [out] out_handle 生成されたハンドルの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param out_handle 成功後にハンドルが書き込まれるので ::voicevox_core_delete で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_new(
+    options: VoicevoxInitializeOptions,
+    out_handle: *mut *mut VoicevoxCoreHandle,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let options = options.try_into_options()?;
+        let internal = Internal::new_with_mutex();
+        internal.lock().unwrap().initialize(options)?;
+        out_handle.write(Box::into_raw(Box::new(VoicevoxCoreHandle(internal))));
+        Ok(())
+    })())
+}
+
+/// #VoicevoxCoreHandle を破棄し、確保していたリソースを解放する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること。この関数の呼び出し後は使用できない
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_delete(handle: *mut VoicevoxCoreHandle) {
+    (*handle).0.lock().unwrap().finalize();
+    drop(Box::from_raw(handle));
+}
+
+/// 指定したハンドルでモデルを読み込む
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] speaker_id 読み込むモデルの話者ID
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_load_model(
+    handle: *mut VoicevoxCoreHandle,
+    speaker_id: u32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error(
+        (*handle)
+            .0
+            .lock()
+            .unwrap()
+            .load_model(speaker_id)
+            .map_err(Into::into),
+    )
+}
+
+/// 指定したハンドルで AudioQuery を実行する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryのオプション
+/// @param [out] output_audio_query_json AudioQuery を json でフォーマットしたもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param text null終端文字列であること
+/// @param output_audio_query_json 自動でheapメモリが割り当てられるので ::voicevox_audio_query_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_audio_query(
+    handle: *mut VoicevoxCoreHandle,
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxAudioQueryOptions,
+    output_audio_query_json: *mut *mut c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = CStr::from_ptr(text);
+        let mut internal = (*handle).0.lock().unwrap();
+        let audio_query = &Internal::audio_query(&mut internal, text, speaker_id, options.into())?;
+        write_json_to_ptr(output_audio_query_json, audio_query);
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルで AudioQuery から音声合成する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::voicevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_synthesis(
+    handle: *mut VoicevoxCoreHandle,
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxSynthesisOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let wav = &(*handle)
+            .0
+            .lock()
+            .unwrap()
+            .synthesis(audio_query, speaker_id, options.into())?;
+        write_wav_to_ptr(output_wav, output_wav_length, wav);
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルでテキスト音声合成を実行する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options テキスト音声合成オプション
+/// @param [out] output_wav_length 出力する wav データのサイズ
+/// @param [out] output_wav wav データの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param text null終端文字列であること
+/// @param output_wav 自動で output_wav_length 分のデータが割り当てられるので ::voicevox_wav_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_tts(
+    handle: *mut VoicevoxCoreHandle,
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxTtsOptions,
+    output_wav_length: *mut usize,
+    output_wav: *mut *mut u8,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let output = (*handle)
+            .0
+            .lock()
+            .unwrap()
+            .tts(text, speaker_id, options.into())?;
+        let (ptr, size) = leak_vec_and_store_length(output);
+        output_wav.write(ptr);
+        output_wav_length.write(size);
+        Ok(())
+    })())
 }
 
 static INTERNAL_BUFFER_VEC_LENGTH: Lazy<Mutex<HashMap<usize, usize>>> =
@@ -382,6 +550,214 @@ pub unsafe extern "C" fn voicevox_audio_query(
     })())
 }
 
+/// テキストを解析し、音素列をjsonで取得する
+/// AudioQueryとは異なり、duration・F0・decodeの推論は行わない
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryのオプション。`kana`を有効にするとaquestalk形式のkanaとして解釈する
+/// @param [out] output_phonemes_json 音素列を json でフォーマットしたもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param text null終端文字列であること
+/// @param output_phonemes_json 自動でheapメモリが割り当てられるので ::voicevox_phonemes_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_text_to_phonemes(
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxAudioQueryOptions,
+    output_phonemes_json: *mut *mut c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = CStr::from_ptr(text);
+        let phonemes =
+            &create_audio_query(text, speaker_id, Internal::text_to_phonemes, options)?;
+        write_json_to_ptr(output_phonemes_json, phonemes);
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルでテキストを解析し、音素列をjsonで取得する
+/// AudioQueryとは異なり、duration・F0・decodeの推論は行わない
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryのオプション。`kana`を有効にするとaquestalk形式のkanaとして解釈する
+/// @param [out] output_phonemes_json 音素列を json でフォーマットしたもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param text null終端文字列であること
+/// @param output_phonemes_json 自動でheapメモリが割り当てられるので ::voicevox_phonemes_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_text_to_phonemes(
+    handle: *mut VoicevoxCoreHandle,
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxAudioQueryOptions,
+    output_phonemes_json: *mut *mut c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = CStr::from_ptr(text);
+        let mut internal = (*handle).0.lock().unwrap();
+        let phonemes =
+            &Internal::text_to_phonemes(&mut internal, text, speaker_id, options.into())?;
+        write_json_to_ptr(output_phonemes_json, phonemes);
+        Ok(())
+    })())
+}
+
+/// ::voicevox_text_to_phonemesで出力されたjsonデータのメモリを解放する
+/// @param [in] phonemes_json 解放する json フォーマットされた音素列データ
+///
+/// # Safety
+/// @param phonemes_json 確保したメモリ領域が破棄される
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_phonemes_json_free(phonemes_json: *mut c_char) {
+    libc::free(phonemes_json as *mut c_void);
+}
+
+/// ユーザー辞書に単語を登録する
+/// OpenJTalkの解析時、組み込み辞書より優先して参照される
+/// @param [in] surface 辞書に登録する単語の表層形
+/// @param [in] pronunciation_kana 単語の読み(カタカナ)
+/// @param [in] accent_type アクセント核位置
+/// @param [in] priority 単語の優先度
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param surface null終端文字列であること
+/// @param pronunciation_kana null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_register_user_word(
+    surface: *const c_char,
+    pronunciation_kana: *const c_char,
+    accent_type: u32,
+    priority: u32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let surface = ensure_utf8(CStr::from_ptr(surface))?;
+        let pronunciation_kana = ensure_utf8(CStr::from_ptr(pronunciation_kana))?;
+        lock_internal().register_user_word(surface, pronunciation_kana, accent_type, priority)?;
+        Ok(())
+    })())
+}
+
+/// ユーザー辞書から単語を削除する
+/// @param [in] surface ::voicevox_register_user_word で登録した単語の表層形
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param surface null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_unregister_user_word(
+    surface: *const c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let surface = ensure_utf8(CStr::from_ptr(surface))?;
+        lock_internal().unregister_user_word(surface)?;
+        Ok(())
+    })())
+}
+
+/// ユーザー辞書をjsonから読み込む
+/// 読み込まれた単語はインスタンスが破棄されるまで保持される
+/// @param [in] path_or_json ユーザー辞書のファイルパス、またはjson文字列そのもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param path_or_json null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_load_user_dict_json(
+    path_or_json: *const c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let path_or_json = ensure_utf8(CStr::from_ptr(path_or_json))?;
+        lock_internal().load_user_dict_json(path_or_json)?;
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルのユーザー辞書に単語を登録する
+/// そのハンドルのOpenJTalkインスタンスでのみ参照され、他のハンドルには影響しない
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] surface 辞書に登録する単語の表層形
+/// @param [in] pronunciation_kana 単語の読み(カタカナ)
+/// @param [in] accent_type アクセント核位置
+/// @param [in] priority 単語の優先度
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param surface null終端文字列であること
+/// @param pronunciation_kana null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_register_user_word(
+    handle: *mut VoicevoxCoreHandle,
+    surface: *const c_char,
+    pronunciation_kana: *const c_char,
+    accent_type: u32,
+    priority: u32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let surface = ensure_utf8(CStr::from_ptr(surface))?;
+        let pronunciation_kana = ensure_utf8(CStr::from_ptr(pronunciation_kana))?;
+        (*handle).0.lock().unwrap().register_user_word(
+            surface,
+            pronunciation_kana,
+            accent_type,
+            priority,
+        )?;
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルのユーザー辞書から単語を削除する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] surface ::voicevox_core_register_user_word で登録した単語の表層形
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param surface null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_unregister_user_word(
+    handle: *mut VoicevoxCoreHandle,
+    surface: *const c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let surface = ensure_utf8(CStr::from_ptr(surface))?;
+        (*handle).0.lock().unwrap().unregister_user_word(surface)?;
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルのユーザー辞書をjsonから読み込む
+/// 読み込まれた単語はそのハンドルが破棄されるまで保持され、他のハンドルには影響しない
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] path_or_json ユーザー辞書のファイルパス、またはjson文字列そのもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param path_or_json null終端文字列であること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_load_user_dict_json(
+    handle: *mut VoicevoxCoreHandle,
+    path_or_json: *const c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let path_or_json = ensure_utf8(CStr::from_ptr(path_or_json))?;
+        (*handle)
+            .0
+            .lock()
+            .unwrap()
+            .load_user_dict_json(path_or_json)?;
+        Ok(())
+    })())
+}
+
 /// `voicevox_synthesis` のオプション
 #[repr(C)]
 pub struct VoicevoxSynthesisOptions {
@@ -472,6 +848,416 @@ pub unsafe extern "C" fn voicevox_tts(
     })())
 }
 
+/// AudioQuery から音声合成し、wavコンテナを介さずf32 PCMを直接取得する
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [out] output_sample_rate 出力するPCMのサンプルレート。audio_query_jsonのoutput_sampling_rateに従う
+/// @param [out] output_channels 出力するPCMのチャンネル数。audio_query_jsonのoutput_stereoに従う(1または2)
+/// @param [out] output_pcm_length 出力するPCMデータのサンプル数
+/// @param [out] output_pcm PCMデータの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param output_pcm 自動で output_pcm_length 分のデータが割り当てられるので ::voicevox_pcm_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_synthesis_raw(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxSynthesisOptions,
+    output_sample_rate: *mut u32,
+    output_channels: *mut u32,
+    output_pcm_length: *mut usize,
+    output_pcm: *mut *mut f32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let wav = lock_internal().synthesis(audio_query, speaker_id, options.into())?;
+        let (sample_rate, channels) = wav_format(&wav);
+        let pcm = wav_to_f32_pcm(&wav);
+        let (ptr, size) = leak_vec_and_store_length(pcm);
+        output_sample_rate.write(sample_rate);
+        output_channels.write(channels as u32);
+        output_pcm_length.write(size);
+        output_pcm.write(ptr);
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルで AudioQuery から音声合成し、wavコンテナを介さずf32 PCMを直接取得する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [out] output_sample_rate 出力するPCMのサンプルレート。audio_query_jsonのoutput_sampling_rateに従う
+/// @param [out] output_channels 出力するPCMのチャンネル数。audio_query_jsonのoutput_stereoに従う(1または2)
+/// @param [out] output_pcm_length 出力するPCMデータのサンプル数
+/// @param [out] output_pcm PCMデータの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param output_pcm 自動で output_pcm_length 分のデータが割り当てられるので ::voicevox_pcm_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_synthesis_raw(
+    handle: *mut VoicevoxCoreHandle,
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxSynthesisOptions,
+    output_sample_rate: *mut u32,
+    output_channels: *mut u32,
+    output_pcm_length: *mut usize,
+    output_pcm: *mut *mut f32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query =
+            &serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        let wav = (*handle)
+            .0
+            .lock()
+            .unwrap()
+            .synthesis(audio_query, speaker_id, options.into())?;
+        let (sample_rate, channels) = wav_format(&wav);
+        let pcm = wav_to_f32_pcm(&wav);
+        let (ptr, size) = leak_vec_and_store_length(pcm);
+        output_sample_rate.write(sample_rate);
+        output_channels.write(channels as u32);
+        output_pcm_length.write(size);
+        output_pcm.write(ptr);
+        Ok(())
+    })())
+}
+
+/// テキスト音声合成を実行し、wavコンテナを介さずf32 PCMを直接取得する
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options テキスト音声合成オプション
+/// @param [out] output_sample_rate 出力するPCMのサンプルレート
+/// @param [out] output_channels 出力するPCMのチャンネル数(1または2)
+/// @param [out] output_pcm_length 出力するPCMデータのサンプル数
+/// @param [out] output_pcm PCMデータの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param text null終端文字列であること
+/// @param output_pcm 自動で output_pcm_length 分のデータが割り当てられるので ::voicevox_pcm_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_tts_raw(
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxTtsOptions,
+    output_sample_rate: *mut u32,
+    output_channels: *mut u32,
+    output_pcm_length: *mut usize,
+    output_pcm: *mut *mut f32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let wav = lock_internal().tts(text, speaker_id, options.into())?;
+        let (sample_rate, channels) = wav_format(&wav);
+        let pcm = wav_to_f32_pcm(&wav);
+        let (ptr, size) = leak_vec_and_store_length(pcm);
+        output_sample_rate.write(sample_rate);
+        output_channels.write(channels as u32);
+        output_pcm_length.write(size);
+        output_pcm.write(ptr);
+        Ok(())
+    })())
+}
+
+/// 指定したハンドルでテキスト音声合成を実行し、wavコンテナを介さずf32 PCMを直接取得する
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options テキスト音声合成オプション
+/// @param [out] output_sample_rate 出力するPCMのサンプルレート
+/// @param [out] output_channels 出力するPCMのチャンネル数(1または2)
+/// @param [out] output_pcm_length 出力するPCMデータのサンプル数
+/// @param [out] output_pcm PCMデータの出力先
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param text null終端文字列であること
+/// @param output_pcm 自動で output_pcm_length 分のデータが割り当てられるので ::voicevox_pcm_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_tts_raw(
+    handle: *mut VoicevoxCoreHandle,
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxTtsOptions,
+    output_sample_rate: *mut u32,
+    output_channels: *mut u32,
+    output_pcm_length: *mut usize,
+    output_pcm: *mut *mut f32,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let wav = (*handle)
+            .0
+            .lock()
+            .unwrap()
+            .tts(text, speaker_id, options.into())?;
+        let (sample_rate, channels) = wav_format(&wav);
+        let pcm = wav_to_f32_pcm(&wav);
+        let (ptr, size) = leak_vec_and_store_length(pcm);
+        output_sample_rate.write(sample_rate);
+        output_channels.write(channels as u32);
+        output_pcm_length.write(size);
+        output_pcm.write(ptr);
+        Ok(())
+    })())
+}
+
+/// ::voicevox_synthesis_raw/::voicevox_tts_rawで出力されたPCMデータを解放する
+/// @param[in] pcm 確保されたメモリ領域
+///
+/// # Safety
+/// @param pcm 実行後に割り当てられたメモリ領域が解放される
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_pcm_free(pcm: *mut f32) {
+    drop(restore_vec(pcm));
+}
+
+/// `voicevox_synthesis_stream`/`voicevox_tts_stream` で生成されたPCMデータを受け取るコールバック
+/// @param user_data 呼び出し時にそのまま渡されるユーザーデータ
+/// @param pcm 生成されたPCMデータ(f32)へのポインタ。チャンネル数・サンプルレートは渡したAudioQueryのoutput_stereo/output_sampling_rateに従う
+/// @param frame_count pcmに含まれるサンプル数
+/// @return 合成を継続する場合はtrue、中断する場合はfalseを返すこと
+///
+/// # Safety
+/// @param pcm この関数の呼び出し中のみ有効。呼び出し後に保持する場合はコピーすること
+pub type VoicevoxSynthesisCallback =
+    unsafe extern "C" fn(user_data: *mut c_void, pcm: *const f32, frame_count: usize) -> bool;
+
+/// AudioQuery から音声合成し、生成されたPCMデータを逐次コールバックに渡す
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [in] callback 生成されたPCMを受け取るコールバック
+/// @param [in] user_data callback にそのまま渡されるユーザーデータ
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param audio_query_json null終端文字列であること
+/// @param callback はロックを保持していない状態で呼ばれるため、内部から他のvoicevox_関数を呼び出しても良い
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_synthesis_stream(
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxSynthesisOptions,
+    callback: VoicevoxSynthesisCallback,
+    user_data: *mut c_void,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query: AudioQueryModel =
+            serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        synthesis_stream(
+            &DEFAULT_HANDLE.0,
+            &audio_query,
+            speaker_id,
+            options.into(),
+            callback,
+            user_data,
+        )
+    })())
+}
+
+/// 指定したハンドルで AudioQuery から音声合成し、生成されたPCMデータを逐次コールバックに渡す
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] audio_query_json jsonフォーマットされた AudioQuery
+/// @param [in] speaker_id 話者ID
+/// @param [in] options AudioQueryから音声合成オプション
+/// @param [in] callback 生成されたPCMを受け取るコールバック
+/// @param [in] user_data callback にそのまま渡されるユーザーデータ
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param audio_query_json null終端文字列であること
+/// @param callback はロックを保持していない状態で呼ばれるため、内部から他のvoicevox_関数を呼び出しても良い
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_synthesis_stream(
+    handle: *mut VoicevoxCoreHandle,
+    audio_query_json: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxSynthesisOptions,
+    callback: VoicevoxSynthesisCallback,
+    user_data: *mut c_void,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let audio_query_json = CStr::from_ptr(audio_query_json)
+            .to_str()
+            .map_err(|_| CApiError::InvalidUtf8Input)?;
+        let audio_query: AudioQueryModel =
+            serde_json::from_str(audio_query_json).map_err(CApiError::InvalidAudioQuery)?;
+        synthesis_stream(
+            &(*handle).0,
+            &audio_query,
+            speaker_id,
+            options.into(),
+            callback,
+            user_data,
+        )
+    })())
+}
+
+/// テキスト音声合成を実行し、生成されたPCMデータを逐次コールバックに渡す
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options テキスト音声合成オプション
+/// @param [in] callback 生成されたPCMを受け取るコールバック
+/// @param [in] user_data callback にそのまま渡されるユーザーデータ
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param text null終端文字列であること
+/// @param callback はロックを保持していない状態で呼ばれるため、内部から他のvoicevox_関数を呼び出しても良い
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_tts_stream(
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxTtsOptions,
+    callback: VoicevoxSynthesisCallback,
+    user_data: *mut c_void,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = ensure_utf8(CStr::from_ptr(text))?;
+        let options: voicevox_core::TtsOptions = options.into();
+        let audio_query = create_audio_query(
+            &CString::new(text).map_err(|_| CApiError::InvalidUtf8Input)?,
+            speaker_id,
+            Internal::audio_query,
+            VoicevoxAudioQueryOptions {
+                kana: options.kana,
+            },
+        )?;
+        synthesis_stream(
+            &DEFAULT_HANDLE.0,
+            &audio_query,
+            speaker_id,
+            voicevox_core::SynthesisOptions {
+                enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            },
+            callback,
+            user_data,
+        )
+    })())
+}
+
+/// 指定したハンドルでテキスト音声合成を実行し、生成されたPCMデータを逐次コールバックに渡す
+/// @param [in] handle ::voicevox_core_new で生成したハンドル
+/// @param [in] text テキスト
+/// @param [in] speaker_id 話者ID
+/// @param [in] options テキスト音声合成オプション
+/// @param [in] callback 生成されたPCMを受け取るコールバック
+/// @param [in] user_data callback にそのまま渡されるユーザーデータ
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param handle 有効な #VoicevoxCoreHandle へのポインタであること
+/// @param text null終端文字列であること
+/// @param callback はロックを保持していない状態で呼ばれるため、内部から他のvoicevox_関数を呼び出しても良い
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_core_tts_stream(
+    handle: *mut VoicevoxCoreHandle,
+    text: *const c_char,
+    speaker_id: u32,
+    options: VoicevoxTtsOptions,
+    callback: VoicevoxSynthesisCallback,
+    user_data: *mut c_void,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let text = CStr::from_ptr(text);
+        let options: voicevox_core::TtsOptions = options.into();
+        let audio_query = Internal::audio_query(
+            &mut (*handle).0.lock().unwrap(),
+            text,
+            speaker_id,
+            VoicevoxAudioQueryOptions {
+                kana: options.kana,
+            }
+            .into(),
+        )?;
+        synthesis_stream(
+            &(*handle).0,
+            &audio_query,
+            speaker_id,
+            voicevox_core::SynthesisOptions {
+                enable_interrogative_upspeak: options.enable_interrogative_upspeak,
+            },
+            callback,
+            user_data,
+        )
+    })())
+}
+
+// AudioQueryのアクセント句ごとに区切ってdecodeし、出来上がったf32 PCM断片から順にcallbackへ渡す
+// 1セグメントぶんデコードするたびにcallbackを呼び、falseが返れば残りのセグメントはデコードせずに
+// 中断することで、全体の合成完了を待たずに先頭の音声から再生を始められるようにする
+// ロックはセグメントごとのsynthesisの間だけ取り、callbackの呼び出し中は保持しない
+fn synthesis_stream(
+    internal: &Mutex<Internal>,
+    audio_query: &AudioQueryModel,
+    speaker_id: u32,
+    options: voicevox_core::SynthesisOptions,
+    callback: VoicevoxSynthesisCallback,
+    user_data: *mut c_void,
+) -> Result<()> {
+    let num_segments = audio_query.accent_phrases.len();
+    for (i, accent_phrase) in audio_query.accent_phrases.iter().enumerate() {
+        let mut segment_query = audio_query.clone();
+        segment_query.accent_phrases = vec![accent_phrase.clone()];
+        // pre_phoneme_length/post_phoneme_lengthは発話全体の先頭・末尾の無音区間のため、
+        // 中間のセグメントにまで引き継ぐと区切りのたびに無音が挟まってしまう
+        if i != 0 {
+            segment_query.pre_phoneme_length = 0.0;
+        }
+        if i != num_segments - 1 {
+            segment_query.post_phoneme_length = 0.0;
+        }
+        let wav = internal
+            .lock()
+            .unwrap()
+            .synthesis(&segment_query, speaker_id, options)?;
+        let pcm = wav_to_f32_pcm(&wav);
+        let continue_synthesis = unsafe { callback(user_data, pcm.as_ptr(), pcm.len()) };
+        if !continue_synthesis {
+            return Err(CApiError::SynthesisCancelled.into());
+        }
+    }
+    Ok(())
+}
+
+// voicevox_synthesisが生成する16bit PCM WAVの先頭44byteのヘッダを読み飛ばし、
+// 残りのサンプルをf32([-1.0, 1.0])へ変換する。チャンネル数に関わらずインターリーブされた
+// サンプル列をそのままf32へ変換するだけなので、チャンネルごとの分離はしない
+pub(crate) fn wav_to_f32_pcm(wav: &[u8]) -> Vec<f32> {
+    const WAV_HEADER_SIZE: usize = 44;
+    wav[WAV_HEADER_SIZE..]
+        .chunks_exact(2)
+        .map(|bytes| (i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32).max(-1.0))
+        .collect()
+}
+
+// wavのfmtチャンクからサンプルレート(bytes 24..28, little-endian)と
+// チャンネル数(bytes 22..24, little-endian)を読み取る
+pub(crate) fn wav_format(wav: &[u8]) -> (u32, u16) {
+    let sample_rate = u32::from_le_bytes([wav[24], wav[25], wav[26], wav[27]]);
+    let channels = u16::from_le_bytes([wav[22], wav[23]]);
+    (sample_rate, channels)
+}
+
 /// jsonフォーマットされた AudioQuery データのメモリを解放する
 /// @param [in] audio_query_json 解放する json フォーマットされた AudioQuery データ
 ///
@@ -526,6 +1312,10 @@ mod tests {
         Err(Error::GetSupportedDevices(anyhow!("some get supported devices error"))),
         VoicevoxResultCode::VOICEVOX_RESULT_GET_SUPPORTED_DEVICES_ERROR
     )]
+    #[case(
+        Err(CApiError::SynthesisCancelled.into()),
+        VoicevoxResultCode::VOICEVOX_RESULT_SYNTHESIS_CANCELLED_ERROR
+    )]
     fn into_result_code_with_error_works(
         #[case] result: Result<()>,
         #[case] expected: VoicevoxResultCode,
@@ -533,4 +1323,43 @@ mod tests {
         let actual = into_result_code_with_error(result.map_err(Into::into));
         assert_eq!(expected, actual);
     }
+
+    #[test]
+    fn voicevox_core_handle_can_be_created_used_and_deleted() {
+        let options = voicevox_make_default_initialize_options();
+        let mut handle: *mut VoicevoxCoreHandle = std::ptr::null_mut();
+        let result = unsafe { voicevox_core_new(options, &mut handle) };
+        if result == VoicevoxResultCode::VOICEVOX_RESULT_OK {
+            assert!(!handle.is_null());
+            // モデルが存在しない環境でもパニックせず結果コードが返ってくることを確認する
+            let _ = unsafe { voicevox_core_load_model(handle, 0) };
+            unsafe { voicevox_core_delete(handle) };
+        } else {
+            assert!(handle.is_null());
+        }
+    }
+
+    // 実際のsynthesisは通さず、44byteのヘッダとそれに続くサンプルだけを持つ最小限のwavを組み立てる
+    fn fake_wav(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+        let mut wav = vec![0_u8; 44];
+        wav[22..24].copy_from_slice(&channels.to_le_bytes());
+        wav[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+        wav.extend(samples.iter().flat_map(|sample| sample.to_le_bytes()));
+        wav
+    }
+
+    #[rstest]
+    #[case(vec![0, 1, -1, i16::MIN, i16::MAX], vec![0.0, 1.0 / i16::MAX as f32, -1.0 / i16::MAX as f32, -1.0, 1.0])]
+    fn wav_to_f32_pcm_works(#[case] samples: Vec<i16>, #[case] expected: Vec<f32>) {
+        let wav = fake_wav(24000, 1, &samples);
+        assert_eq!(expected, wav_to_f32_pcm(&wav));
+    }
+
+    #[rstest]
+    #[case(24000, 1)]
+    #[case(48000, 2)]
+    fn wav_format_works(#[case] sample_rate: u32, #[case] channels: u16) {
+        let wav = fake_wav(sample_rate, channels, &[]);
+        assert_eq!((sample_rate, channels), wav_format(&wav));
+    }
 }
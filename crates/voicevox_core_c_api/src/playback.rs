@@ -0,0 +1,271 @@
+// VOICEVOXが生成するwavをcpal経由でそのまま再生するための再生サブシステム
+// ヘッドレス環境ではcpalごと不要なため、`playback` featureの背後に隠してある
+use super::helpers::*;
+use super::{wav_format, wav_to_f32_pcm, VoicevoxResultCode};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{SampleFormat, Stream};
+use std::collections::VecDeque;
+use std::ffi::c_char;
+use std::sync::{Arc, Mutex};
+
+/// 音声出力デバイスを表す不透明なハンドル
+pub struct VoicevoxAudioPlayer {
+    stream: Stream,
+    device_sample_rate: u32,
+    device_channels: u16,
+    queue: Arc<Mutex<VecDeque<f32>>>,
+}
+
+// キューにはデバイスのチャンネル数にインターリーブ済みのサンプルを積んであるため、
+// ストリームのデータコールバックへはそのまま1サンプルずつ取り出して書き込めばよい
+fn feed_from_queue(queue: &Mutex<VecDeque<f32>>, out: &mut [f32]) {
+    let mut queue = queue.lock().unwrap();
+    for out_sample in out.iter_mut() {
+        *out_sample = queue.pop_front().unwrap_or(0.0);
+    }
+}
+
+// wavのチャンネル数のまま、実際のデバイスのネイティブサンプルレートへ線形補間でリサンプルする。
+// 多くの出力デバイスはVOICEVOXの出力サンプルレートをネイティブにサポートしないため、
+// 再生前に必ずデバイス側のレートへ合わせる
+fn resample_linear(samples: &[f32], channels: u16, from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let channels = channels as usize;
+    let frame_count = samples.len() / channels;
+    let ratio = to_rate as f64 / from_rate as f64;
+    let out_frame_count = ((frame_count as f64) * ratio).round() as usize;
+    (0..out_frame_count)
+        .flat_map(|i| {
+            let src_pos = i as f64 / ratio;
+            let idx = (src_pos.floor() as usize).min(frame_count - 1);
+            let next_idx = (idx + 1).min(frame_count - 1);
+            let frac = src_pos - idx as f64;
+            (0..channels).map(move |ch| {
+                let a = samples[idx * channels + ch];
+                let b = samples[next_idx * channels + ch];
+                (a as f64 + (b as f64 - a as f64) * frac) as f32
+            })
+        })
+        .collect()
+}
+
+// インターリーブされたサンプル列のチャンネル数を、デバイスのチャンネル数に合わせて変換する。
+// チャンネル数が一致する場合はそのまま、一致しない場合はモノラルにダウンミックスしてから
+// デバイスの全チャンネルへ複製する
+fn to_device_layout(samples: &[f32], source_channels: u16, device_channels: u16) -> Vec<f32> {
+    let source_channels = source_channels as usize;
+    let device_channels = device_channels as usize;
+    if source_channels == device_channels {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(source_channels)
+        .flat_map(|frame| {
+            let mono = frame.iter().sum::<f32>() / source_channels as f32;
+            std::iter::repeat(mono).take(device_channels)
+        })
+        .collect()
+}
+
+/// デフォルトの音声出力デバイスを開く
+/// デバイスがネイティブにサポートするサンプルレート・チャンネル数でストリームを構築し、
+/// ::voicevox_player_play に渡されたVOICEVOXのwavはその設定に合わせてリサンプルされる
+/// @return 開かれた #VoicevoxAudioPlayer へのポインタ。失敗した場合はnull
+///
+/// # Safety
+/// 使い終わったら ::voicevox_player_close で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_open_default_output_device() -> *mut VoicevoxAudioPlayer {
+    let host = cpal::default_host();
+    let device = match host.default_output_device() {
+        Some(device) => device,
+        None => {
+            tracing::error!("デフォルトの音声出力デバイスが見つかりませんでした");
+            return std::ptr::null_mut();
+        }
+    };
+    let supported_config = match device.default_output_config() {
+        Ok(supported_config) => supported_config,
+        Err(err) => {
+            tracing::error!("出力デバイスの設定取得に失敗しました: {err}");
+            return std::ptr::null_mut();
+        }
+    };
+    let sample_format = supported_config.sample_format();
+    if sample_format != SampleFormat::F32 {
+        tracing::error!(
+            "未対応のサンプルフォーマットです: {sample_format:?}(f32のみ対応しています)"
+        );
+        return std::ptr::null_mut();
+    }
+    let config: cpal::StreamConfig = supported_config.config();
+    let device_sample_rate = config.sample_rate.0;
+    let device_channels = config.channels;
+
+    let queue = Arc::new(Mutex::new(VecDeque::new()));
+    let stream_queue = Arc::clone(&queue);
+    let stream = match device.build_output_stream(
+        &config,
+        move |out: &mut [f32], _| feed_from_queue(&stream_queue, out),
+        |err| tracing::error!("再生ストリームでエラーが発生しました: {err}"),
+        None,
+    ) {
+        Ok(stream) => stream,
+        Err(err) => {
+            tracing::error!("再生ストリームの構築に失敗しました: {err}");
+            return std::ptr::null_mut();
+        }
+    };
+    if let Err(err) = stream.play() {
+        tracing::error!("再生ストリームの開始に失敗しました: {err}");
+        return std::ptr::null_mut();
+    }
+    Box::into_raw(Box::new(VoicevoxAudioPlayer {
+        stream,
+        device_sample_rate,
+        device_channels,
+        queue,
+    }))
+}
+
+/// wavデータを再生キューに投入する
+/// @param [in] player ::voicevox_open_default_output_device で開いたプレイヤー
+/// @param [in] wav wavデータ
+/// @param [in] wav_length wavデータの長さ
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param player 有効な #VoicevoxAudioPlayer へのポインタであること
+/// @param wav wav_length分のデータがある状態で渡すこと
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_player_play(
+    player: *mut VoicevoxAudioPlayer,
+    wav: *const u8,
+    wav_length: usize,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let player = &*player;
+        let wav = std::slice::from_raw_parts(wav, wav_length);
+        let (sample_rate, channels) = wav_format(wav);
+        let samples = wav_to_f32_pcm(wav);
+        let samples = resample_linear(&samples, channels, sample_rate, player.device_sample_rate);
+        let samples = to_device_layout(&samples, channels, player.device_channels);
+        player.queue.lock().unwrap().extend(samples);
+        Ok(())
+    })())
+}
+
+/// 再生キューが空になるまで待機する
+/// @param [in] player ::voicevox_open_default_output_device で開いたプレイヤー
+///
+/// # Safety
+/// @param player 有効な #VoicevoxAudioPlayer へのポインタであること
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_player_wait(player: *mut VoicevoxAudioPlayer) {
+    let player = &*player;
+    loop {
+        if player.queue.lock().unwrap().is_empty() {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+}
+
+/// プレイヤーを閉じ、確保していたリソースを解放する
+/// @param [in] player ::voicevox_open_default_output_device で開いたプレイヤー
+///
+/// # Safety
+/// @param player 有効な #VoicevoxAudioPlayer へのポインタであること。この関数の呼び出し後は使用できない
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_player_close(player: *mut VoicevoxAudioPlayer) {
+    drop(Box::from_raw(player));
+}
+
+/// 利用可能な音声出力デバイスの一覧をjsonで取得する
+/// @param [out] output_devices_json 出力デバイス一覧をjsonでフォーマットしたもの
+/// @return 結果コード #VoicevoxResultCode
+///
+/// # Safety
+/// @param output_devices_json 自動でheapメモリが割り当てられるので ::voicevox_output_devices_json_free で解放する必要がある
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_get_output_devices_json(
+    output_devices_json: *mut *mut c_char,
+) -> VoicevoxResultCode {
+    into_result_code_with_error((|| {
+        let host = cpal::default_host();
+        let names: Vec<String> = host
+            .output_devices()
+            .map_err(|e| CApiError::GetOutputDevices(anyhow::Error::from(e)))?
+            .filter_map(|device| device.name().ok())
+            .collect();
+        write_json_to_ptr(output_devices_json, &names);
+        Ok(())
+    })())
+}
+
+/// ::voicevox_get_output_devices_json で取得したjsonデータのメモリを解放する
+/// @param [in] output_devices_json 解放するjsonフォーマットされたデータ
+///
+/// # Safety
+/// @param output_devices_json 確保したメモリ領域が破棄される
+#[no_mangle]
+pub unsafe extern "C" fn voicevox_output_devices_json_free(output_devices_json: *mut c_char) {
+    libc::free(output_devices_json as *mut libc::c_void);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rstest::*;
+
+    #[rstest]
+    fn resample_linear_identity_is_noop() {
+        let samples = vec![0.0, 0.5, -0.5, 1.0];
+        assert_eq!(samples, resample_linear(&samples, 1, 24000, 24000));
+    }
+
+    #[rstest]
+    fn resample_linear_upsamples() {
+        let samples = vec![0.0, 1.0];
+        let resampled = resample_linear(&samples, 1, 1, 2);
+        assert_eq!(4, resampled.len());
+        assert_eq!(0.0, resampled[0]);
+        assert_eq!(1.0, resampled[resampled.len() - 1]);
+    }
+
+    #[rstest]
+    fn resample_linear_downsamples() {
+        let samples = vec![0.0, 0.25, 0.5, 0.75, 1.0];
+        let resampled = resample_linear(&samples, 1, 4, 2);
+        assert_eq!(3, resampled.len());
+    }
+
+    #[rstest]
+    fn resample_linear_keeps_channels_interleaved() {
+        // 左右で異なる値を持つステレオ信号を補間しても、チャンネルがまたがって混ざらないことを確認する
+        let samples = vec![0.0, 1.0, 1.0, 0.0];
+        let resampled = resample_linear(&samples, 2, 1, 2);
+        assert_eq!(vec![0.0, 1.0, 0.5, 0.5, 1.0, 0.0, 1.0, 0.0], resampled);
+    }
+
+    #[rstest]
+    fn to_device_layout_passes_through_when_channels_match() {
+        let samples = vec![0.1, 0.2, 0.3, 0.4];
+        assert_eq!(samples, to_device_layout(&samples, 2, 2));
+    }
+
+    #[rstest]
+    fn to_device_layout_fans_out_mono_to_stereo() {
+        let samples = vec![0.5, -0.5];
+        assert_eq!(vec![0.5, 0.5, -0.5, -0.5], to_device_layout(&samples, 1, 2));
+    }
+
+    #[rstest]
+    fn to_device_layout_downmixes_stereo_to_mono() {
+        let samples = vec![1.0, 0.0];
+        assert_eq!(vec![0.5], to_device_layout(&samples, 2, 1));
+    }
+}